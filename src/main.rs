@@ -1,8 +1,15 @@
-use clap::Parser;
+// The `fuzzing` build only ever runs `fuzz_ingest`, so the CLI/sweep surface
+// below it is legitimately unused in that configuration; keep that build
+// clean under `-D warnings` without hiding dead code in normal builds.
+#![cfg_attr(feature = "fuzzing", allow(dead_code))]
+
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -42,6 +49,70 @@ struct Cli {
     /// If provided, overrides built-in grids.
     #[arg(long = "grid-file")]
     grid_file: Option<PathBuf>,
+
+    /// Execution backend for the sweep.
+    #[arg(long = "runner", value_enum, default_value_t = RunnerKind::Sync)]
+    runner: RunnerKind,
+
+    /// Worker count for the parallel runner. 0 = detect from available cores.
+    #[arg(long = "threads", default_value_t = 0)]
+    threads: usize,
+
+    /// Output sinks each candidate is streamed through, comma-separated.
+    #[arg(long = "emit", value_enum, value_delimiter = ',', default_value = "jsonl")]
+    emit: Vec<EmitKind>,
+
+    /// Reject candidates whose backtest drawdown exceeds this fraction.
+    #[arg(long = "max-dd")]
+    max_dd: Option<f64>,
+
+    /// Reject candidates whose backtest profit factor is below this.
+    #[arg(long = "min-pf")]
+    min_pf: Option<f64>,
+
+    /// Reject candidates with fewer than this many trades.
+    #[arg(long = "min-trades")]
+    min_trades: Option<u32>,
+
+    /// Halt the sweep once this many candidates have been accepted.
+    #[arg(long = "budget-n")]
+    budget_n: Option<usize>,
+
+    /// Halt the sweep once this many milliseconds of wall-clock have elapsed.
+    #[arg(long = "budget-ms")]
+    budget_ms: Option<u128>,
+
+    /// Sample this many random parameter sets instead of enumerating the grid.
+    #[arg(long = "random-search")]
+    random_search: Option<usize>,
+
+    /// Seed for random-search sampling; the same seed reproduces a run exactly.
+    #[arg(long = "seed", default_value_t = 0)]
+    seed: u64,
+
+    /// Abort with a nonzero exit if any tick rows are malformed.
+    #[arg(long = "strict", default_value_t = false)]
+    strict: bool,
+}
+
+/// A destination candidates are streamed to as they are produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EmitKind {
+    /// Append each candidate to `./out/candidates.jsonl`.
+    Jsonl,
+    /// Write candidates to `./out/candidates.csv`.
+    Csv,
+    /// Emit one JSON line per candidate to stdout for a live consumer.
+    Stdout,
+}
+
+/// Which execution backend drives the sweep.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RunnerKind {
+    /// Single-threaded, evaluates combos in grid order.
+    Sync,
+    /// Fans the cartesian product of combos out across a worker pool.
+    Parallel,
 }
 
 // -------- data structures -------- //
@@ -53,6 +124,20 @@ struct Tick {
     ask: f64,
 }
 
+// Accounting for a single `load_ticks` pass. Lets the caller decide whether to
+// continue (lenient) or abort (strict), and drives the quarantine file.
+#[derive(Debug, Default)]
+struct ParseReport {
+    lines_read: usize,
+    ticks_ok: usize,
+    malformed: Vec<(usize, String)>,
+    zero_price_rows: usize,
+    out_of_order_ts: usize,
+}
+
+// OHLC bar with an averaged spread. The dummy `run_strategy` only reads the
+// count today, but the fields are the bar's contract for the real strategy.
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct Candle {
     start_ts: u64,
@@ -89,12 +174,76 @@ struct CandidateOut {
     metrics: Metrics,
 }
 
+// One fully-resolved point in the parameter space. The sweep expands the
+// grid axes into a flat list of these so runners can treat every combo as an
+// independent, embarrassingly-parallel unit of work.
+#[derive(Debug, Clone)]
+struct SweepCombo {
+    donch_n: u32,
+    rr_min: f64,
+    max_spread: f64,
+    session_filter: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct GridSpec {
+    /// Enumerated donch_n values. May be absent if `donch_n_range` is given.
+    #[serde(default)]
     donch_n: Vec<u32>,
+    /// Enumerated rr_min values. May be absent if `rr_min_range` is given.
+    #[serde(default)]
     rr_min: Vec<f64>,
+    /// Enumerated session_filter values. Always required (no range mode).
+    #[serde(default)]
     session_filter: Vec<String>,
+    /// Enumerated max_spread values. May be absent if `max_spread_range` is given.
+    #[serde(default)]
     max_spread: Vec<f64>,
+    /// Optional ward thresholds carried alongside the grid itself.
+    #[serde(default)]
+    wards: Option<WardConfig>,
+    /// Optional integer range for `donch_n`, used by random-search mode.
+    #[serde(default)]
+    donch_n_range: Option<IntRange>,
+    /// Optional continuous range for `rr_min`, used by random-search mode.
+    #[serde(default)]
+    rr_min_range: Option<FloatRange>,
+    /// Optional continuous range for `max_spread`, used by random-search mode.
+    #[serde(default)]
+    max_spread_range: Option<FloatRange>,
+}
+
+// Inclusive integer range with an optional stride, e.g. `{min,max,step}`.
+#[derive(Debug, Clone, Deserialize)]
+struct IntRange {
+    min: u32,
+    max: u32,
+    #[serde(default)]
+    step: Option<u32>,
+}
+
+// Continuous `[min, max)` range for a float axis, e.g. `{min,max}`.
+#[derive(Debug, Clone, Deserialize)]
+struct FloatRange {
+    min: f64,
+    max: f64,
+}
+
+// Ward thresholds, as they may appear under `"wards"` in a grid JSON file.
+// Any field left unset is simply not installed as a ward. Matching CLI flags
+// take precedence over these values.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct WardConfig {
+    #[serde(default)]
+    max_dd: Option<f64>,
+    #[serde(default)]
+    min_pf: Option<f64>,
+    #[serde(default)]
+    min_trades: Option<u32>,
+    #[serde(default)]
+    budget_n: Option<usize>,
+    #[serde(default)]
+    budget_ms: Option<u128>,
 }
 
 // -------- helpers -------- //
@@ -106,31 +255,126 @@ fn now_millis() -> u128 {
         .as_millis()
 }
 
-// Load ticks from CSV with header: ts_ms,bid,ask
-fn load_ticks(csv_path: &PathBuf) -> anyhow::Result<Vec<Tick>> {
+// Load ticks from a CSV file with header: ts_ms,bid,ask
+fn load_ticks(csv_path: &PathBuf) -> anyhow::Result<(Vec<Tick>, ParseReport)> {
     let f = File::open(csv_path)?;
-    let reader = BufReader::new(f);
+    parse_ticks(BufReader::new(f))
+}
 
+// Parse ticks from any reader, accumulating a ParseReport instead of silently
+// coercing bad fields. Rows with an unparseable timestamp, too few fields, or a
+// non-positive / non-finite price are rejected rather than zero-filled; rows
+// whose `ts_ms` goes backwards are kept but counted. This is on the hot path
+// for untrusted files, so it degrades gracefully and never panics.
+fn parse_ticks<R: BufRead>(reader: R) -> anyhow::Result<(Vec<Tick>, ParseReport)> {
     let mut out: Vec<Tick> = Vec::new();
+    let mut report = ParseReport::default();
+    let mut last_ts: Option<u64> = None;
+
     for (i, line_res) in reader.lines().enumerate() {
         let line = line_res?;
-        if i == 0 {
-            // assume header row, skip
-            if line.contains("ts_ms") && line.contains("bid") && line.contains("ask") {
-                continue;
-            }
+        report.lines_read += 1;
+
+        if i == 0 && line.contains("ts_ms") && line.contains("bid") && line.contains("ask") {
+            // header row
+            continue;
         }
+
         let parts: Vec<&str> = line.split(',').collect();
         if parts.len() < 3 {
+            report.malformed.push((i, "fewer than 3 fields".to_string()));
             continue;
         }
-        let ts_ms: u64 = parts[0].trim().parse().unwrap_or(0);
-        let bid: f64 = parts[1].trim().parse().unwrap_or(0.0);
-        let ask: f64 = parts[2].trim().parse().unwrap_or(0.0);
+
+        let ts_ms = match parts[0].trim().parse::<u64>() {
+            Ok(v) => v,
+            Err(_) => {
+                report
+                    .malformed
+                    .push((i, format!("unparseable ts_ms: {:?}", parts[0].trim())));
+                continue;
+            }
+        };
+        let bid = match parts[1].trim().parse::<f64>() {
+            Ok(v) => v,
+            Err(_) => {
+                report
+                    .malformed
+                    .push((i, format!("unparseable bid: {:?}", parts[1].trim())));
+                continue;
+            }
+        };
+        let ask = match parts[2].trim().parse::<f64>() {
+            Ok(v) => v,
+            Err(_) => {
+                report
+                    .malformed
+                    .push((i, format!("unparseable ask: {:?}", parts[2].trim())));
+                continue;
+            }
+        };
+
+        if !(bid.is_finite() && ask.is_finite() && bid > 0.0 && ask > 0.0) {
+            report.zero_price_rows += 1;
+            report
+                .malformed
+                .push((i, format!("non-positive price: bid={bid} ask={ask}")));
+            continue;
+        }
+
+        if let Some(prev) = last_ts {
+            if ts_ms < prev {
+                report.out_of_order_ts += 1;
+            }
+        }
+        last_ts = Some(ts_ms);
 
         out.push(Tick { ts_ms, bid, ask });
+        report.ticks_ok += 1;
+    }
+
+    Ok((out, report))
+}
+
+// Quote a CSV field: wrap in double quotes and double any embedded quotes so
+// commas or quotes in the value can't shift the row into extra columns.
+fn csv_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+// Write quarantined rows to ./out/rejected_ticks.csv for later inspection.
+fn write_rejected_ticks(malformed: &[(usize, String)]) -> anyhow::Result<()> {
+    ensure_out_dir()?;
+    let mut f = BufWriter::new(File::create("./out/rejected_ticks.csv")?);
+    writeln!(f, "line,reason")?;
+    for (line_no, reason) in malformed {
+        writeln!(f, "{},{}", line_no, csv_quote(reason))?;
     }
-    Ok(out)
+    f.flush()?;
+    Ok(())
+}
+
+// Surface the ingestion report: quarantine malformed rows, log a summary to
+// stderr, and in strict mode abort with a nonzero exit if anything was bad.
+fn report_ingestion(report: &ParseReport, strict: bool) -> anyhow::Result<()> {
+    if !report.malformed.is_empty() {
+        write_rejected_ticks(&report.malformed)?;
+    }
+    eprintln!(
+        "[ingest] lines_read={} ticks_ok={} malformed={} zero_price_rows={} out_of_order_ts={}",
+        report.lines_read,
+        report.ticks_ok,
+        report.malformed.len(),
+        report.zero_price_rows,
+        report.out_of_order_ts
+    );
+    if strict && !report.malformed.is_empty() {
+        anyhow::bail!(
+            "strict ingestion: {} malformed row(s); see ./out/rejected_ticks.csv",
+            report.malformed.len()
+        );
+    }
+    Ok(())
 }
 
 // Naive candle builder: bucket ticks into fixed interval_ms windows.
@@ -141,8 +385,9 @@ fn ticks_to_candles(ticks: &[Tick], interval_ms: u64) -> Vec<Candle> {
 
     let mut candles: Vec<Candle> = Vec::new();
 
+    let interval_ms = interval_ms.max(1);
     let mut bucket_start = ticks[0].ts_ms;
-    let mut bucket_end = bucket_start + interval_ms;
+    let mut bucket_end = bucket_start.saturating_add(interval_ms);
     let mut cur_open = ticks[0].bid;
     let mut cur_high = ticks[0].bid;
     let mut cur_low = ticks[0].bid;
@@ -152,7 +397,12 @@ fn ticks_to_candles(ticks: &[Tick], interval_ms: u64) -> Vec<Candle> {
     let mut spread_cnt: u32 = 0;
 
     for tk in ticks {
-        if tk.ts_ms >= bucket_end {
+        // `bucket_end > bucket_start` is required for the tick to have
+        // actually crossed a boundary: once bucket_start saturates at
+        // u64::MAX, bucket_end can't advance any further, so every
+        // remaining tick (including one with ts_ms == u64::MAX) belongs to
+        // that same terminal bucket rather than triggering endless flushes.
+        if tk.ts_ms >= bucket_end && bucket_end > bucket_start {
             // flush old candle
             let mid_spread = if spread_cnt > 0 {
                 spread_sum / (spread_cnt as f64)
@@ -169,10 +419,19 @@ fn ticks_to_candles(ticks: &[Tick], interval_ms: u64) -> Vec<Candle> {
                 mid_spread,
             });
 
-            // advance bucket
-            while tk.ts_ms >= bucket_end {
+            // Advance bucket directly to the one containing `tk`, rather than
+            // stepping one interval at a time: a tick with a huge ts_ms gap
+            // (or adversarial input near u64::MAX) would otherwise spin this
+            // loop for as many intervals as the gap spans.
+            let skipped = (tk.ts_ms - bucket_start) / interval_ms;
+            bucket_start = bucket_start.saturating_add(skipped.saturating_mul(interval_ms));
+            bucket_end = bucket_start.saturating_add(interval_ms);
+            // Only saturation at the u64 ceiling can leave a single tick still
+            // past `bucket_end`; one more step (not a loop) settles it without
+            // risking a hang when ts_ms is itself u64::MAX.
+            if tk.ts_ms >= bucket_end {
                 bucket_start = bucket_end;
-                bucket_end = bucket_start + interval_ms;
+                bucket_end = bucket_start.saturating_add(interval_ms);
             }
 
             // reset state for new candle with current tick
@@ -317,39 +576,13 @@ fn ensure_out_dir() -> anyhow::Result<()> {
     Ok(())
 }
 
-// Write jsonl lines for all candidates
-fn write_candidates_jsonl(cands: &[CandidateOut]) -> anyhow::Result<()> {
-    ensure_out_dir()?;
-    let mut f = File::create("./out/candidates.jsonl")?;
-    for c in cands {
-        let line = serde_json::to_string(c)?;
-        writeln!(f, "{}", line)?;
-    }
-    Ok(())
-}
-
-// Write top-k to top_candidates.jsonl
-fn write_top_candidates_jsonl(cands: &[CandidateOut], top_k: usize) -> anyhow::Result<usize> {
-    ensure_out_dir()?;
-    let mut v: Vec<CandidateOut> = cands.to_vec();
-    // sort by score desc
-    v.sort_by(|a, b| score_candidate(b).partial_cmp(&score_candidate(a)).unwrap());
-
-    let kept = std::cmp::min(top_k, v.len());
-    let mut f = File::create("./out/top_candidates.jsonl")?;
-    for i in 0..kept {
-        let line = serde_json::to_string(&v[i])?;
-        writeln!(f, "{}", line)?;
-    }
-    Ok(kept)
-}
+// The four enumerated sweep axes: donch_n, rr_min, session_filter, max_spread.
+type GridAxes = (Vec<u32>, Vec<f64>, Vec<String>, Vec<f64>);
 
 // Build sweep grid:
 // - if grid_file is provided, load GridSpec JSON from disk
 // - else, fall back to the built-in mini grid (your current ~18 combos)
-fn build_param_grid(
-    maybe_grid_file: &Option<PathBuf>,
-) -> anyhow::Result<(Vec<u32>, Vec<f64>, Vec<String>, Vec<f64>)> {
+fn build_param_grid(maybe_grid_file: &Option<PathBuf>) -> anyhow::Result<GridAxes> {
     if let Some(grid_path) = maybe_grid_file {
         let raw = fs::read_to_string(grid_path)?;
         let spec: GridSpec = serde_json::from_str(&raw)?;
@@ -370,47 +603,745 @@ fn build_param_grid(
     Ok((donch_n, rr_min, session_filter, max_spread))
 }
 
+// Load the full grid spec, either from `--grid-file` or the built-in default.
+// Random-search mode needs the whole spec (ranges included), not just the axes.
+fn load_grid_spec(maybe_grid_file: &Option<PathBuf>) -> anyhow::Result<GridSpec> {
+    if let Some(grid_path) = maybe_grid_file {
+        let raw = fs::read_to_string(grid_path)?;
+        let spec: GridSpec = serde_json::from_str(&raw)?;
+        return Ok(spec);
+    }
+
+    Ok(GridSpec {
+        donch_n: vec![20, 30, 40],
+        rr_min: vec![1.8, 2.0, 2.5],
+        session_filter: vec!["london".to_string(), "nyopen".to_string()],
+        max_spread: vec![2.5],
+        wards: None,
+        donch_n_range: None,
+        rr_min_range: None,
+        max_spread_range: None,
+    })
+}
+
+// A small seedable PRNG (SplitMix64). Pure-std stand-in for `StdRng`: given a
+// seed it produces a fixed stream, which is all random-search reproducibility
+// needs.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform f64 in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Uniform index in [0, n); returns 0 for n == 0.
+    fn index(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next_u64() % n as u64) as usize
+        }
+    }
+}
+
+// Draw a single `donch_n` value, honoring an explicit integer range (with
+// optional stride) when present, otherwise sampling the enumerated axis.
+fn sample_donch_n(spec: &GridSpec, rng: &mut SplitMix64) -> u32 {
+    if let Some(range) = &spec.donch_n_range {
+        let (lo, hi) = (range.min.min(range.max), range.min.max(range.max));
+        match range.step {
+            Some(step) if step > 0 => {
+                let steps = ((hi - lo) / step) as usize;
+                lo + (rng.index(steps + 1) as u32) * step
+            }
+            _ => lo + (rng.index((hi - lo) as usize + 1) as u32),
+        }
+    } else {
+        spec.donch_n[rng.index(spec.donch_n.len())]
+    }
+}
+
+// Draw a single float value from an explicit continuous range when present,
+// otherwise from the enumerated axis.
+fn sample_float(range: &Option<FloatRange>, axis: &[f64], rng: &mut SplitMix64) -> f64 {
+    if let Some(r) = range {
+        let (lo, hi) = (r.min.min(r.max), r.min.max(r.max));
+        lo + rng.next_f64() * (hi - lo)
+    } else {
+        axis[rng.index(axis.len())]
+    }
+}
+
+// Every axis must be samplable: an enumerated axis needs at least one value
+// unless its matching range supplies the bounds. `session_filter` is
+// categorical with no range, so it must always be non-empty. Bails with a
+// clear error rather than letting the sampler index an empty slice.
+fn validate_sampling_spec(spec: &GridSpec) -> anyhow::Result<()> {
+    if spec.donch_n.is_empty() && spec.donch_n_range.is_none() {
+        anyhow::bail!("random-search: `donch_n` is empty and no `donch_n_range` was given");
+    }
+    if spec.rr_min.is_empty() && spec.rr_min_range.is_none() {
+        anyhow::bail!("random-search: `rr_min` is empty and no `rr_min_range` was given");
+    }
+    if spec.max_spread.is_empty() && spec.max_spread_range.is_none() {
+        anyhow::bail!("random-search: `max_spread` is empty and no `max_spread_range` was given");
+    }
+    if spec.session_filter.is_empty() {
+        anyhow::bail!("random-search: `session_filter` has no values to sample");
+    }
+    Ok(())
+}
+
+// Sample `n` parameter sets from the spec's ranges (or enumerated axes) using a
+// seeded RNG. Gives uniform coverage of large spaces on a fixed budget.
+fn sample_combos(spec: &GridSpec, n: usize, seed: u64) -> anyhow::Result<Vec<SweepCombo>> {
+    validate_sampling_spec(spec)?;
+    let mut rng = SplitMix64::new(seed);
+    let mut combos = Vec::with_capacity(n);
+    for _ in 0..n {
+        combos.push(SweepCombo {
+            donch_n: sample_donch_n(spec, &mut rng),
+            rr_min: sample_float(&spec.rr_min_range, &spec.rr_min, &mut rng),
+            max_spread: sample_float(&spec.max_spread_range, &spec.max_spread, &mut rng),
+            session_filter: spec.session_filter[rng.index(spec.session_filter.len())].clone(),
+        });
+    }
+    Ok(combos)
+}
+
+// Flatten the grid axes into the cartesian product of combos, in the same
+// order the old quadruple-nested loop produced them.
+fn expand_grid(
+    donch_vec: &[u32],
+    rr_vec: &[f64],
+    sess_vec: &[String],
+    spread_vec: &[f64],
+) -> Vec<SweepCombo> {
+    let mut combos = Vec::new();
+    for &d in donch_vec {
+        for &rr in rr_vec {
+            for sess in sess_vec {
+                for &sp in spread_vec {
+                    combos.push(SweepCombo {
+                        donch_n: d,
+                        rr_min: rr,
+                        max_spread: sp,
+                        session_filter: sess.clone(),
+                    });
+                }
+            }
+        }
+    }
+    combos
+}
+
+// Evaluate a single combo into a candidate. Pure over (candles, combo), which
+// is what lets the parallel runner fan combos out without any shared state.
+fn eval_combo(symbol: &str, candles: &[Candle], combo: &SweepCombo) -> CandidateOut {
+    let metrics = run_strategy(
+        candles,
+        combo.donch_n,
+        combo.rr_min,
+        combo.max_spread,
+        &combo.session_filter,
+    );
+    build_candidate(
+        symbol,
+        combo.donch_n,
+        combo.rr_min,
+        combo.max_spread,
+        &combo.session_filter,
+        metrics,
+    )
+}
+
+// -------- runners -------- //
+
+// Whether the sweep should keep producing candidates or stop now. Returned by
+// the runner's sink so a ward can halt compute, not just truncate output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    Continue,
+    Halt,
+}
+
+// Execution backend for a sweep. `run` evaluates combos in grid order and feeds
+// each candidate to `sink` as soon as it is produced; it stops launching work
+// once the sink returns `Flow::Halt`, so `--budget-n`/`--budget-ms` bound
+// compute rather than just the emitted set.
+trait Runner {
+    fn run(
+        &self,
+        grid: &[SweepCombo],
+        symbol: &str,
+        candles: &[Candle],
+        sink: &mut dyn FnMut(CandidateOut) -> anyhow::Result<Flow>,
+    ) -> anyhow::Result<()>;
+}
+
+// Current behavior: evaluate every combo on the calling thread, in order.
+struct SyncRunner;
+
+impl Runner for SyncRunner {
+    fn run(
+        &self,
+        grid: &[SweepCombo],
+        symbol: &str,
+        candles: &[Candle],
+        sink: &mut dyn FnMut(CandidateOut) -> anyhow::Result<Flow>,
+    ) -> anyhow::Result<()> {
+        for combo in grid {
+            let cand = eval_combo(symbol, candles, combo);
+            if sink(cand)? == Flow::Halt {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Evaluate combos a batch at a time across a pool of scoped worker threads,
+// then feed each batch's candidates through the (single-threaded) sink in grid
+// order. Each worker processes a contiguous *slice* of the batch, so a batch
+// spawns `threads` threads rather than one per combo. `run_strategy` is pure,
+// so workers only borrow the immutable candle slice. Halt is re-checked between
+// batches, so at most one batch's worth of combos is computed past the budget.
+struct ParallelRunner {
+    threads: usize,
+}
+
+// Combos each worker handles per batch. Keeps the thread spawn count at
+// `threads` per batch (not one per combo) while bounding over-computation on
+// halt to `threads * GRAIN` combos. Kept small so a BudgetWard HaltSweep still
+// stops the sweep close to the requested budget (see HaltSweep fix above).
+const PARALLEL_GRAIN: usize = 4;
+
+impl Runner for ParallelRunner {
+    fn run(
+        &self,
+        grid: &[SweepCombo],
+        symbol: &str,
+        candles: &[Candle],
+        sink: &mut dyn FnMut(CandidateOut) -> anyhow::Result<Flow>,
+    ) -> anyhow::Result<()> {
+        let threads = self.threads.max(1);
+        let batch_size = threads.saturating_mul(PARALLEL_GRAIN);
+
+        for batch in grid.chunks(batch_size) {
+            let slice_size = batch.len().div_ceil(threads);
+            let produced: Vec<CandidateOut> = std::thread::scope(|scope| {
+                let handles: Vec<_> = batch
+                    .chunks(slice_size)
+                    .map(|slice| {
+                        scope.spawn(move || {
+                            slice
+                                .iter()
+                                .map(|combo| eval_combo(symbol, candles, combo))
+                                .collect::<Vec<CandidateOut>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|h| h.join().expect("sweep worker thread panicked"))
+                    .collect()
+            });
+            for cand in produced {
+                if sink(cand)? == Flow::Halt {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Resolve the CLI selection into a concrete runner. A thread count of 0 asks
+// the parallel runner to size itself from the machine's available cores.
+fn build_runner(kind: RunnerKind, threads: usize) -> Box<dyn Runner> {
+    match kind {
+        RunnerKind::Sync => Box::new(SyncRunner),
+        RunnerKind::Parallel => {
+            let threads = if threads == 0 {
+                std::thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(1)
+            } else {
+                threads
+            };
+            Box::new(ParallelRunner { threads })
+        }
+    }
+}
+
+// -------- output processors -------- //
+
+// A sink in the output pipeline. Each candidate produced by the sweep is fed
+// to `on_candidate` as soon as it exists; `finish` flushes any buffered or
+// ranking state once the sweep is done. Processors own their own IO so the
+// generation step no longer has to buffer the whole result set.
+trait OutputProcessor {
+    fn on_candidate(&mut self, c: &CandidateOut) -> anyhow::Result<()>;
+    fn finish(&mut self) -> anyhow::Result<()>;
+}
+
+// Append every candidate to `./out/candidates.jsonl`, one JSON object per line.
+struct JsonlFileProcessor {
+    writer: BufWriter<File>,
+}
+
+impl JsonlFileProcessor {
+    fn new() -> anyhow::Result<Self> {
+        ensure_out_dir()?;
+        let f = File::create("./out/candidates.jsonl")?;
+        Ok(Self {
+            writer: BufWriter::new(f),
+        })
+    }
+}
+
+impl OutputProcessor for JsonlFileProcessor {
+    fn on_candidate(&mut self, c: &CandidateOut) -> anyhow::Result<()> {
+        writeln!(self.writer, "{}", serde_json::to_string(c)?)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// Write candidates to `./out/candidates.csv` with a flat header.
+struct CsvProcessor {
+    writer: BufWriter<File>,
+    wrote_header: bool,
+}
+
+impl CsvProcessor {
+    fn new() -> anyhow::Result<Self> {
+        ensure_out_dir()?;
+        let f = File::create("./out/candidates.csv")?;
+        Ok(Self {
+            writer: BufWriter::new(f),
+            wrote_header: false,
+        })
+    }
+}
+
+impl OutputProcessor for CsvProcessor {
+    fn on_candidate(&mut self, c: &CandidateOut) -> anyhow::Result<()> {
+        if !self.wrote_header {
+            writeln!(
+                self.writer,
+                "model_id,symbol,donch_n,rr_min,max_spread,session_filter,pf_bt,dd_bt,trades,winrate,pnl"
+            )?;
+            self.wrote_header = true;
+        }
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            csv_quote(&c.model_id),
+            csv_quote(&c.symbol),
+            c.params.donch_n,
+            c.params.rr_min,
+            c.params.max_spread,
+            csv_quote(&c.params.session_filter),
+            c.metrics.pf_bt,
+            c.metrics.dd_bt,
+            c.metrics.trades,
+            c.metrics.winrate,
+            c.metrics.pnl,
+        )?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// Emit one JSON line per candidate to stdout so a downstream control plane can
+// consume candidates live over a pipe. Each line is tagged `"type":"candidate"`
+// so a consumer can tell candidates apart from the end-of-run summary line
+// (tagged `"type":"summary"`) on the same stream. Flushed per line.
+struct StdoutProcessor {
+    writer: BufWriter<std::io::Stdout>,
+}
+
+impl StdoutProcessor {
+    fn new() -> Self {
+        Self {
+            writer: BufWriter::new(std::io::stdout()),
+        }
+    }
+}
+
+impl OutputProcessor for StdoutProcessor {
+    fn on_candidate(&mut self, c: &CandidateOut) -> anyhow::Result<()> {
+        let mut v = serde_json::to_value(c)?;
+        if let Some(obj) = v.as_object_mut() {
+            obj.insert("type".to_string(), serde_json::json!("candidate"));
+        }
+        writeln!(self.writer, "{}", serde_json::to_string(&v)?)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// A candidate paired with its score, ordered by score so it can live in a heap.
+struct ScoredCandidate {
+    score: f64,
+    candidate: CandidateOut,
+}
+
+impl PartialEq for ScoredCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score
+    }
+}
+
+impl Eq for ScoredCandidate {}
+
+impl PartialOrd for ScoredCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredCandidate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score
+            .partial_cmp(&other.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }
+}
+
+// Keep only the best K candidates by `score_candidate`, bounding memory to K
+// regardless of grid size. Backed by a min-heap so the weakest kept candidate
+// is evicted once the heap is full. Writes `./out/top_candidates.jsonl` on
+// finish and reports how many were kept.
+struct TopKHeapProcessor {
+    k: usize,
+    heap: BinaryHeap<Reverse<ScoredCandidate>>,
+}
+
+impl TopKHeapProcessor {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::new(),
+        }
+    }
+}
+
+impl OutputProcessor for TopKHeapProcessor {
+    fn on_candidate(&mut self, c: &CandidateOut) -> anyhow::Result<()> {
+        if self.k == 0 {
+            return Ok(());
+        }
+        self.heap.push(Reverse(ScoredCandidate {
+            score: score_candidate(c),
+            candidate: c.clone(),
+        }));
+        if self.heap.len() > self.k {
+            // drop the current weakest
+            self.heap.pop();
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        ensure_out_dir()?;
+        let mut ranked: Vec<ScoredCandidate> =
+            std::mem::take(&mut self.heap).into_iter().map(|r| r.0).collect();
+        // best first
+        ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut f = BufWriter::new(File::create("./out/top_candidates.jsonl")?);
+        for sc in &ranked {
+            writeln!(f, "{}", serde_json::to_string(&sc.candidate)?)?;
+        }
+        f.flush()?;
+        Ok(())
+    }
+}
+
+// Assemble the processor chain: the top-K heap always runs (it drives
+// `top_candidates.jsonl` and the summary count), plus one processor per
+// selected `--emit` sink.
+fn build_output_chain(
+    emit: &[EmitKind],
+    top_k: usize,
+) -> anyhow::Result<Vec<Box<dyn OutputProcessor>>> {
+    let mut chain: Vec<Box<dyn OutputProcessor>> = vec![Box::new(TopKHeapProcessor::new(top_k))];
+    for kind in emit {
+        match kind {
+            EmitKind::Jsonl => chain.push(Box::new(JsonlFileProcessor::new()?)),
+            EmitKind::Csv => chain.push(Box::new(CsvProcessor::new()?)),
+            EmitKind::Stdout => chain.push(Box::new(StdoutProcessor::new())),
+        }
+    }
+    Ok(chain)
+}
+
+// -------- wards -------- //
+
+// A ward's ruling on a single candidate, evaluated before it reaches the
+// output pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WardVerdict {
+    /// Let the candidate through to the output pipeline.
+    Accept,
+    /// Drop this candidate but keep sweeping.
+    Reject,
+    /// Stop the sweep entirely; no further candidates are considered.
+    HaltSweep,
+}
+
+// A guard consulted for each candidate. Rejection wards filter junk combos;
+// the budget ward bounds how much a sweep produces or how long it runs.
+trait Ward {
+    fn check(&self, c: &CandidateOut) -> WardVerdict;
+}
+
+// Reject candidates whose backtest drawdown exceeds `threshold`.
+struct MaxDrawdownWard {
+    threshold: f64,
+}
+
+impl Ward for MaxDrawdownWard {
+    fn check(&self, c: &CandidateOut) -> WardVerdict {
+        if c.metrics.dd_bt > self.threshold {
+            WardVerdict::Reject
+        } else {
+            WardVerdict::Accept
+        }
+    }
+}
+
+// Reject candidates whose backtest profit factor is below `threshold`.
+struct MinProfitFactorWard {
+    threshold: f64,
+}
+
+impl Ward for MinProfitFactorWard {
+    fn check(&self, c: &CandidateOut) -> WardVerdict {
+        if c.metrics.pf_bt < self.threshold {
+            WardVerdict::Reject
+        } else {
+            WardVerdict::Accept
+        }
+    }
+}
+
+// Reject candidates with fewer than `threshold` trades.
+struct MinTradesWard {
+    threshold: u32,
+}
+
+impl Ward for MinTradesWard {
+    fn check(&self, c: &CandidateOut) -> WardVerdict {
+        if c.metrics.trades < self.threshold {
+            WardVerdict::Reject
+        } else {
+            WardVerdict::Accept
+        }
+    }
+}
+
+// Halt the sweep once enough candidates have been accepted or a wall-clock
+// budget is spent. Only consulted for candidates that already passed the
+// rejection wards, so its count tracks accepted candidates. Uses interior
+// mutability because `check` takes `&self` like every other ward.
+struct BudgetWard {
+    max_accepted: Option<usize>,
+    max_ms: Option<u128>,
+    started_ms: u128,
+    accepted: std::cell::Cell<usize>,
+}
+
+impl BudgetWard {
+    fn new(max_accepted: Option<usize>, max_ms: Option<u128>) -> Self {
+        Self {
+            max_accepted,
+            max_ms,
+            started_ms: now_millis(),
+            accepted: std::cell::Cell::new(0),
+        }
+    }
+}
+
+impl Ward for BudgetWard {
+    fn check(&self, _c: &CandidateOut) -> WardVerdict {
+        if let Some(limit) = self.max_ms {
+            if now_millis().saturating_sub(self.started_ms) >= limit {
+                return WardVerdict::HaltSweep;
+            }
+        }
+        if let Some(limit) = self.max_accepted {
+            if self.accepted.get() >= limit {
+                return WardVerdict::HaltSweep;
+            }
+        }
+        self.accepted.set(self.accepted.get() + 1);
+        WardVerdict::Accept
+    }
+}
+
+// Walk the ward chain in order, returning the first non-Accept verdict. The
+// budget ward is kept last so it only counts candidates that cleared the
+// rejection wards.
+fn evaluate_wards(wards: &[Box<dyn Ward>], c: &CandidateOut) -> WardVerdict {
+    for w in wards {
+        match w.check(c) {
+            WardVerdict::Accept => {}
+            other => return other,
+        }
+    }
+    WardVerdict::Accept
+}
+
+// Assemble the ward chain from CLI flags layered over any `wards` block in the
+// grid JSON. Flags win over JSON when both are set.
+fn build_ward_chain(cli: &Cli, json_cfg: &WardConfig) -> Vec<Box<dyn Ward>> {
+    let mut wards: Vec<Box<dyn Ward>> = Vec::new();
+
+    if let Some(threshold) = cli.max_dd.or(json_cfg.max_dd) {
+        wards.push(Box::new(MaxDrawdownWard { threshold }));
+    }
+    if let Some(threshold) = cli.min_pf.or(json_cfg.min_pf) {
+        wards.push(Box::new(MinProfitFactorWard { threshold }));
+    }
+    if let Some(threshold) = cli.min_trades.or(json_cfg.min_trades) {
+        wards.push(Box::new(MinTradesWard { threshold }));
+    }
+
+    let budget_n = cli.budget_n.or(json_cfg.budget_n);
+    let budget_ms = cli.budget_ms.or(json_cfg.budget_ms);
+    if budget_n.is_some() || budget_ms.is_some() {
+        wards.push(Box::new(BudgetWard::new(budget_n, budget_ms)));
+    }
+
+    wards
+}
+
+// Pull just the ward config out of a grid JSON file, if one was provided.
+fn load_ward_config(maybe_grid_file: &Option<PathBuf>) -> anyhow::Result<WardConfig> {
+    if let Some(grid_path) = maybe_grid_file {
+        let raw = fs::read_to_string(grid_path)?;
+        let spec: GridSpec = serde_json::from_str(&raw)?;
+        return Ok(spec.wards.unwrap_or_default());
+    }
+    Ok(WardConfig::default())
+}
+
 // -------- main flow -------- //
 
+// Honggfuzz entry point. Compiled only when the `fuzzing` feature pulls in the
+// honggfuzz dependency (see Cargo.toml); normal builds never reference it. Feeds
+// arbitrary bytes through both ingestion stages to prove they never panic.
+#[cfg(feature = "fuzzing")]
+fn fuzz_ingest() {
+    use std::io::Cursor;
+    loop {
+        honggfuzz::fuzz!(|data: &[u8]| {
+            if let Ok((ticks, _report)) = parse_ticks(Cursor::new(data)) {
+                let _ = ticks_to_candles(&ticks, 60_000);
+            }
+        });
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+fn main() {
+    fuzz_ingest();
+}
+
+#[cfg(not(feature = "fuzzing"))]
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     // load ticks, build candles (1m candles = 60000 ms)
-    let ticks = load_ticks(&cli.ticks_file)?;
+    let (ticks, report) = load_ticks(&cli.ticks_file)?;
+    report_ingestion(&report, cli.strict)?;
     let candles = ticks_to_candles(&ticks, 60_000);
 
     if cli.sweep {
         // SWEEP MODE
-        // Build param grid either from --grid-file or from default
-        let (donch_vec, rr_vec, sess_vec, spread_vec) = build_param_grid(&cli.grid_file)?;
-
-        let mut all_candidates: Vec<CandidateOut> = Vec::new();
-
-        for &d in &donch_vec {
-            for &rr in &rr_vec {
-                for sess in &sess_vec {
-                    for &sp in &spread_vec {
-                        let metrics = run_strategy(&candles, d, rr, sp, sess);
-                        let cand =
-                            build_candidate(&cli.symbol, d, rr, sp, sess.as_str(), metrics);
-                        all_candidates.push(cand);
+        // Either sample N random parameter sets or enumerate the full grid.
+        let combos = if let Some(n) = cli.random_search {
+            let spec = load_grid_spec(&cli.grid_file)?;
+            sample_combos(&spec, n, cli.seed)?
+        } else {
+            let (donch_vec, rr_vec, sess_vec, spread_vec) = build_param_grid(&cli.grid_file)?;
+            expand_grid(&donch_vec, &rr_vec, &sess_vec, &spread_vec)
+        };
+
+        let runner = build_runner(cli.runner, cli.threads);
+
+        // gate each candidate through the ward chain as the runner produces it,
+        // then stream the survivors through the configured output pipeline; a
+        // HaltSweep verdict stops the runner before it computes the rest.
+        const TOP_K: usize = 5;
+        let json_ward_cfg = load_ward_config(&cli.grid_file)?;
+        let wards = build_ward_chain(&cli, &json_ward_cfg);
+        let mut processors = build_output_chain(&cli.emit, TOP_K)?;
+
+        let mut generated: usize = 0;
+        let mut emitted: usize = 0;
+        runner.run(&combos, &cli.symbol, &candles, &mut |c| {
+            generated += 1;
+            match evaluate_wards(&wards, &c) {
+                WardVerdict::Accept => {
+                    for p in processors.iter_mut() {
+                        p.on_candidate(&c)?;
                     }
+                    emitted += 1;
+                    Ok(Flow::Continue)
                 }
+                WardVerdict::Reject => Ok(Flow::Continue),
+                WardVerdict::HaltSweep => Ok(Flow::Halt),
             }
+        })?;
+        for p in processors.iter_mut() {
+            p.finish()?;
         }
 
-        // write full list
-        write_candidates_jsonl(&all_candidates)?;
-
-        // write ranked top 5
-        let kept = write_top_candidates_jsonl(&all_candidates, 5)?;
-
         // print final summary to stdout (this is what your PS expects)
-        let summary = serde_json::json!({
+        let mut summary = serde_json::json!({
+            "type": "summary",
             "ok": true,
-            "generated": all_candidates.len(),
-            "top_kept": kept
+            "generated": generated,
+            "emitted": emitted,
+            "top_kept": std::cmp::min(TOP_K, emitted)
         });
+        if cli.random_search.is_some() {
+            // record mode + seed so the run is exactly reproducible
+            summary["mode"] = serde_json::json!("random");
+            summary["seed"] = serde_json::json!(cli.seed);
+        }
         println!("{}", serde_json::to_string(&summary)?);
 
         return Ok(());
@@ -452,3 +1383,300 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A candidate with just the metrics the wards look at filled in.
+    fn mk(trades: u32, pf_bt: f64, dd_bt: f64) -> CandidateOut {
+        CandidateOut {
+            model_id: "t".to_string(),
+            symbol: "XAUUSD".to_string(),
+            params: ParamsOut {
+                donch_n: 20,
+                rr_min: 2.0,
+                max_spread: 2.5,
+                session_filter: "london".to_string(),
+            },
+            metrics: Metrics {
+                pf_bt,
+                dd_bt,
+                trades,
+                winrate: 0.5,
+                pnl: 1.0,
+            },
+        }
+    }
+
+    #[test]
+    fn sync_runner_stops_computing_on_halt() {
+        let grid: Vec<SweepCombo> = (0..20)
+            .map(|i| SweepCombo {
+                donch_n: 20 + i,
+                rr_min: 2.0,
+                max_spread: 2.5,
+                session_filter: "london".to_string(),
+            })
+            .collect();
+
+        let mut seen = 0usize;
+        SyncRunner
+            .run(&grid, "XAUUSD", &[], &mut |_c| {
+                seen += 1;
+                if seen >= 3 {
+                    Ok(Flow::Halt)
+                } else {
+                    Ok(Flow::Continue)
+                }
+            })
+            .unwrap();
+        // only the combos up to the halt are ever computed, not all 20
+        assert_eq!(seen, 3);
+    }
+
+    #[test]
+    fn budget_ward_halts_after_n_accepted() {
+        let ward = BudgetWard::new(Some(3), None);
+        let c = mk(2000, 1.8, 0.05);
+        assert_eq!(ward.check(&c), WardVerdict::Accept);
+        assert_eq!(ward.check(&c), WardVerdict::Accept);
+        assert_eq!(ward.check(&c), WardVerdict::Accept);
+        assert_eq!(ward.check(&c), WardVerdict::HaltSweep);
+    }
+
+    #[test]
+    fn rejection_wards_run_before_budget_is_charged() {
+        // min-trades first, then a budget of one accepted candidate
+        let wards: Vec<Box<dyn Ward>> = vec![
+            Box::new(MinTradesWard { threshold: 1500 }),
+            Box::new(BudgetWard::new(Some(1), None)),
+        ];
+        let junk = mk(100, 1.8, 0.05); // fails min-trades
+        let good = mk(2000, 1.8, 0.05);
+
+        // junk is rejected and must not consume the budget
+        assert_eq!(evaluate_wards(&wards, &junk), WardVerdict::Reject);
+        assert_eq!(evaluate_wards(&wards, &junk), WardVerdict::Reject);
+        // the one budget slot is still available for the first good candidate
+        assert_eq!(evaluate_wards(&wards, &good), WardVerdict::Accept);
+        // now the budget is spent
+        assert_eq!(evaluate_wards(&wards, &good), WardVerdict::HaltSweep);
+    }
+
+    #[test]
+    fn sync_and_parallel_runners_agree() {
+        let grid: Vec<SweepCombo> = (0..17)
+            .map(|i| SweepCombo {
+                donch_n: 20 + i,
+                rr_min: 1.5 + (i as f64) * 0.1,
+                max_spread: 2.5,
+                session_filter: "london".to_string(),
+            })
+            .collect();
+
+        let collect = |r: &dyn Runner| {
+            let mut out = Vec::new();
+            r.run(&grid, "XAUUSD", &[], &mut |c| {
+                out.push((c.params.donch_n, c.metrics.pf_bt, c.metrics.dd_bt));
+                Ok(Flow::Continue)
+            })
+            .unwrap();
+            out
+        };
+
+        assert_eq!(collect(&SyncRunner), collect(&ParallelRunner { threads: 4 }));
+    }
+
+    #[test]
+    fn parallel_runner_stops_computing_near_halt() {
+        let grid: Vec<SweepCombo> = (0..40)
+            .map(|i| SweepCombo {
+                donch_n: 20 + i,
+                rr_min: 2.0,
+                max_spread: 2.5,
+                session_filter: "london".to_string(),
+            })
+            .collect();
+
+        let seen = std::sync::atomic::AtomicUsize::new(0);
+        ParallelRunner { threads: 4 }
+            .run(&grid, "XAUUSD", &[], &mut |_c| {
+                let n = seen.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                if n >= 3 {
+                    Ok(Flow::Halt)
+                } else {
+                    Ok(Flow::Continue)
+                }
+            })
+            .unwrap();
+        // at most one batch's worth (threads * PARALLEL_GRAIN) of over-computation past the halt
+        assert!(seen.load(std::sync::atomic::Ordering::SeqCst) <= 3 + 4 * PARALLEL_GRAIN);
+    }
+
+    fn spec_with(
+        donch_n: Vec<u32>,
+        session_filter: Vec<String>,
+        donch_n_range: Option<IntRange>,
+    ) -> GridSpec {
+        GridSpec {
+            donch_n,
+            rr_min: vec![1.8, 2.0],
+            session_filter,
+            max_spread: vec![2.5],
+            wards: None,
+            donch_n_range,
+            rr_min_range: None,
+            max_spread_range: None,
+        }
+    }
+
+    #[test]
+    fn csv_quote_wraps_commas_and_doubles_quotes() {
+        assert_eq!(csv_quote("london,extra"), "\"london,extra\"");
+        assert_eq!(csv_quote("a\"b"), "\"a\"\"b\"");
+        assert_eq!(csv_quote("plain"), "\"plain\"");
+    }
+
+    #[test]
+    fn top_k_heap_keeps_only_the_best_k() {
+        let mut p = TopKHeapProcessor::new(2);
+        // score_candidate == pf_bt when dd_bt == 0
+        for pf in [1.0, 4.0, 2.0, 3.0] {
+            p.on_candidate(&mk(1000, pf, 0.0)).unwrap();
+        }
+        assert_eq!(p.heap.len(), 2, "heap is bounded to K, not the full stream");
+        let mut scores: Vec<f64> = p.heap.iter().map(|r| r.0.score).collect();
+        scores.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(scores, vec![3.0, 4.0], "keeps the two highest-scoring");
+    }
+
+    #[test]
+    fn sample_combos_errors_on_empty_categorical_axis() {
+        // empty session_filter, no range can cover it
+        let spec = spec_with(
+            vec![20],
+            vec![],
+            Some(IntRange {
+                min: 10,
+                max: 50,
+                step: None,
+            }),
+        );
+        let err = sample_combos(&spec, 5, 7).unwrap_err();
+        assert!(err.to_string().contains("session_filter"));
+    }
+
+    #[test]
+    fn grid_spec_parses_with_only_ranges_and_no_enumerated_axes() {
+        // the primary random-search use case: a grid file that supplies only
+        // `*_range` bounds, omitting the enumerated `donch_n`/`rr_min`/`max_spread`
+        // fields entirely (and even `session_filter`, which validate_sampling_spec
+        // still rejects since it has no range form).
+        let json = r#"{
+            "donch_n_range": {"min": 10, "max": 50},
+            "rr_min_range": {"min": 1.5, "max": 3.0},
+            "max_spread_range": {"min": 1.0, "max": 3.0}
+        }"#;
+        let spec: GridSpec = serde_json::from_str(json).unwrap();
+        assert!(spec.donch_n.is_empty());
+        assert!(spec.rr_min.is_empty());
+        assert!(spec.max_spread.is_empty());
+        assert!(spec.session_filter.is_empty());
+
+        // enumerated axes are empty but covered by ranges, so sampling succeeds
+        let spec = GridSpec {
+            session_filter: vec!["london".to_string()],
+            ..spec
+        };
+        assert!(sample_combos(&spec, 5, 1).is_ok());
+    }
+
+    #[test]
+    fn sample_combos_is_reproducible_for_a_seed() {
+        let spec = spec_with(
+            vec![20, 30, 40],
+            vec!["london".to_string(), "nyopen".to_string()],
+            None,
+        );
+        let a = sample_combos(&spec, 16, 42).unwrap();
+        let b = sample_combos(&spec, 16, 42).unwrap();
+        let keys = |v: &[SweepCombo]| {
+            v.iter()
+                .map(|c| (c.donch_n, c.rr_min, c.session_filter.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&a), keys(&b), "same seed reproduces the draw exactly");
+    }
+
+    #[test]
+    fn parse_ticks_skips_header_and_keeps_good_rows() {
+        let csv = "ts_ms,bid,ask\n1000,1.5,1.6\n2000,1.7,1.8\n";
+        let (ticks, report) = parse_ticks(csv.as_bytes()).unwrap();
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(report.ticks_ok, 2);
+        assert!(report.malformed.is_empty());
+    }
+
+    #[test]
+    fn parse_ticks_rejects_unparseable_and_nonpositive() {
+        // short row, unparseable ts, non-positive bid, negative ask, clean row
+        let csv = "ts_ms,bid,ask\noops\nNaNts,1.5,1.6\n3000,0,1.6\n4000,1.5,-1.0\n5000,1.5,1.6\n";
+        let (ticks, report) = parse_ticks(csv.as_bytes()).unwrap();
+        assert_eq!(ticks.len(), 1, "only the final clean row survives");
+        assert_eq!(report.malformed.len(), 4);
+        assert_eq!(report.zero_price_rows, 2);
+    }
+
+    #[test]
+    fn parse_ticks_counts_out_of_order_timestamps() {
+        let csv = "ts_ms,bid,ask\n3000,1.5,1.6\n1000,1.5,1.6\n";
+        let (ticks, report) = parse_ticks(csv.as_bytes()).unwrap();
+        assert_eq!(ticks.len(), 2, "out-of-order rows are kept in lenient mode");
+        assert_eq!(report.out_of_order_ts, 1);
+    }
+
+    #[test]
+    fn parse_ticks_never_panics_on_arbitrary_bytes() {
+        // the property the fuzz harness asserts, as a cheap regression test
+        for seed in 0u64..256 {
+            let mut rng = SplitMix64::new(seed);
+            let len = rng.index(64);
+            let bytes: Vec<u8> = (0..len).map(|_| (rng.next_u64() & 0xff) as u8).collect();
+            if let Ok((ticks, _report)) = parse_ticks(&bytes[..]) {
+                let _ = ticks_to_candles(&ticks, 60_000);
+            }
+        }
+    }
+
+    #[test]
+    fn ticks_to_candles_never_panics_near_u64_max_timestamp() {
+        // a single row with ts_ms == u64::MAX must not overflow bucket_end
+        let ticks = vec![Tick {
+            ts_ms: u64::MAX,
+            bid: 1.5,
+            ask: 1.6,
+        }];
+        let candles = ticks_to_candles(&ticks, 60_000);
+        assert_eq!(candles.len(), 1);
+
+        // a huge gap between ticks must not spin the bucket-advance loop
+        let ticks = vec![
+            Tick {
+                ts_ms: 0,
+                bid: 1.5,
+                ask: 1.6,
+            },
+            Tick {
+                ts_ms: u64::MAX - 1,
+                bid: 1.5,
+                ask: 1.6,
+            },
+        ];
+        // the bucket jumps straight to the one containing the second tick
+        // instead of overflowing or spinning for ~(u64::MAX / 60_000)
+        // iterations; each tick lands in its own (far-apart) bucket
+        let candles = ticks_to_candles(&ticks, 60_000);
+        assert_eq!(candles.len(), 2);
+    }
+}